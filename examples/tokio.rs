@@ -1,4 +1,5 @@
-use futures_util::SinkExt;
+use futures_util::StreamExt;
+use libturms::models::phoenix::Event;
 use libturms::websocket::*;
 
 const LOCAL_URL: &str = "http://localhost:4000";
@@ -11,10 +12,24 @@ async fn main() {
         .await
         .expect("Is the password wrong? Or server offline?");
 
-    // To avoid the end of program, we use the second loop here.
-    // However, if we have another program running (such as a web server), we could use another
-    // ``tokio::spawn`.
-    tokio::spawn(receiver);
+    let mut messages = ws.messages();
+    tokio::spawn(async move {
+        while let Some(message) = messages.next().await {
+            match message {
+                Ok(message) if message.event == Event::UnreadMessages => {
+                    println!("Pending messages: {}", message.payload);
+                },
+                Ok(message) => {
+                    println!("{:?}: {}", message.event, message.payload)
+                },
+                Err(error) => eprintln!("Invalid message: {error:?}"),
+            }
+        }
+    });
 
-    loop {}
+    let disconnected = receiver.await;
+    eprintln!(
+        "Discovery server lost after {} attempts: {:?}",
+        disconnected.attempts, disconnected.error
+    );
 }
@@ -0,0 +1,109 @@
+//! Ways to authenticate against the discovery server.
+//!
+//! Every authentication method ends up with a JWT, used to open the
+//! WebSocket connection.
+
+use crate::error::{Error, ErrorType, IoError};
+use crate::models::response::{Response, Status};
+use std::fmt;
+
+/// Closure returning a JWT, see [`AuthMethod::Custom`].
+pub type TokenProvider = Box<dyn Fn() -> Result<String, Error> + Send + Sync>;
+
+/// How to obtain the JWT used to connect to the discovery server.
+pub enum AuthMethod {
+    /// Send vanity and password to the `/api/auth` endpoint.
+    Password {
+        /// Vanity of the user.
+        identifier: String,
+        /// Password, if the server requires one.
+        password: Option<String>,
+    },
+    /// Use a token issued beforehand.
+    Token(String),
+    /// Call a closure to get the token, e.g. for OAuth or magic links.
+    ///
+    /// It is called again on every reconnection.
+    Custom(TokenProvider),
+}
+
+impl fmt::Debug for AuthMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print secrets.
+        match self {
+            AuthMethod::Password { identifier, .. } => f
+                .debug_struct("Password")
+                .field("identifier", identifier)
+                .finish_non_exhaustive(),
+            AuthMethod::Token(_) => f.write_str("Token"),
+            AuthMethod::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+impl AuthMethod {
+    /// Create an [`AuthMethod::Password`].
+    pub fn password<T: AsRef<str>>(identifier: T, password: Option<T>) -> Self {
+        AuthMethod::Password {
+            identifier: identifier.as_ref().to_owned(),
+            password: password.map(|password| password.as_ref().to_owned()),
+        }
+    }
+
+    /// Create an [`AuthMethod::Custom`].
+    pub fn custom<F>(provider: F) -> Self
+    where
+        F: Fn() -> Result<String, Error> + Send + Sync + 'static,
+    {
+        AuthMethod::Custom(Box::new(provider))
+    }
+
+    /// Get a JWT, `auth_url` is only requested by [`AuthMethod::Password`].
+    pub(crate) fn token(
+        &self,
+        agent: &ureq::Agent,
+        auth_url: &str,
+    ) -> Result<String, Error> {
+        match self {
+            AuthMethod::Password {
+                identifier,
+                password,
+            } => {
+                let response = agent
+                    .post(auth_url)
+                    .send_json(ureq::json!({
+                        "vanity": identifier,
+                        "password": password,
+                    }))
+                    .map_err(|error| {
+                        Error::new(
+                            ErrorType::InputOutput(IoError::HTTPError),
+                            Some(Box::new(error)),
+                            None,
+                        )
+                    })?
+                    .into_json::<Response>()
+                    .map_err(|error| {
+                        Error::new(
+                            ErrorType::InputOutput(IoError::HTTPError),
+                            Some(Box::new(error)),
+                            Some("Received invalid JSON response.".to_owned()),
+                        )
+                    })?;
+
+                if response.status == Status::Error || response.data.is_empty()
+                {
+                    return Err(Error::new(
+                        ErrorType::InputOutput(IoError::Credidentials),
+                        None,
+                        Some("Authentication failed.".to_owned()),
+                    ));
+                }
+
+                Ok(response.data)
+            },
+            AuthMethod::Token(token) => Ok(token.clone()),
+            AuthMethod::Custom(provider) => provider(),
+        }
+    }
+}
@@ -80,6 +80,14 @@ pub enum IoError {
     ConnectionError,
     /// Message haven't been sent.
     SendError,
+    /// Message haven't been received.
+    ReceiveError,
+    /// Server did not answer in time.
+    TimeoutError,
+    /// Server refused to join a topic.
+    JoinError,
+    /// WebSocket settings cannot be used.
+    ConfigError,
 }
 
 impl fmt::Display for IoError {
@@ -103,6 +111,18 @@ impl fmt::Display for IoError {
             IoError::SendError => {
                 write!(f, "WebSocket message failed to be sent.")
             },
+            IoError::ReceiveError => {
+                write!(f, "WebSocket message failed to be received.")
+            },
+            IoError::TimeoutError => {
+                write!(f, "Server did not answer in time.")
+            },
+            IoError::JoinError => {
+                write!(f, "Server refused to join topic.")
+            },
+            IoError::ConfigError => {
+                write!(f, "Invalid WebSocket configuration.")
+            },
         }
     }
 }
@@ -117,6 +137,10 @@ pub enum TokenError {
     Expired,
     /// JWT is used too early.
     Early,
+    /// JWT is not meant for this audience.
+    Audience,
+    /// JWT is not issued by the expected issuer.
+    Issuer,
 }
 
 impl fmt::Display for TokenError {
@@ -127,6 +151,12 @@ impl fmt::Display for TokenError {
             },
             TokenError::Expired => write!(f, "Invalid token: expired."),
             TokenError::Early => write!(f, "Invalid token: used too early."),
+            TokenError::Audience => {
+                write!(f, "Invalid token: not meant for this audience.")
+            },
+            TokenError::Issuer => {
+                write!(f, "Invalid token: unexpected issuer.")
+            },
         }
     }
 }
@@ -1,13 +1,43 @@
+//! Background tasks bound to the WebSocket connection.
+
+use crate::log::{log_sensitive, Redaction};
+use crate::websocket::{Messages, Reader, Sender};
+use futures_util::{SinkExt, StreamExt};
+use tokio::time::{interval, Duration};
+use tungstenite::protocol::Message;
+
+use std::sync::PoisonError;
+
+/// Read incoming messages and keep the connection alive.
+pub(crate) async fn handle_and_heartbeat(
+    heartbeat_delay: Duration,
+    mut reader: Reader,
+    writer: Sender,
+    redaction: Redaction,
+    messages: Messages,
+) {
+    let mut heartbeat_interval = interval(heartbeat_delay);
+
     loop {
         tokio::select! {
             // Handler for receiving and printing messages from the server
             message = reader.next() => {
                 match message {
-                    Some(Ok(msg)) => {
-                        if let Ok(message) = msg.into_text() {
-                            println!("Message: {:?}", message);
-                        }
+                    Some(Ok(Message::Text(message))) => {
+                        log_sensitive!(redaction.redacts_messages(), "Message", message);
+                        // No one listening is not an error.
+                        let _ = messages
+                            .read()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .send(message);
+                    }
+                    Some(Ok(Message::Close(_))) => {
+                        println!("Connection closed by the server.");
+                        break;
                     }
+                    // Pings are answered by tungstenite, pongs only keep
+                    // the connection alive.
+                    Some(Ok(_)) => {}
                     Some(Err(e)) => {
                         eprintln!("Error receiving message: {:?}", e);
                         break; // Optionally handle disconnection here
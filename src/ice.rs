@@ -0,0 +1,66 @@
+//! ICE servers configuration.
+//!
+//! Provisioning APIs and environment variables usually give ICE servers as a
+//! list of URIs, e.g. `turn:user:secret@turn.domain.tld:3478?transport=udp`.
+
+use crate::error::{Error, ErrorType, IoError};
+use webrtc::ice::url::{SchemeType, Url};
+use webrtc::ice_transport::ice_credential_type::RTCIceCredentialType;
+
+pub use webrtc::ice_transport::ice_server::RTCIceServer;
+
+/// Parse a STUN or TURN URI into a [`RTCIceServer`].
+///
+/// Accepted form is `scheme:[username:credential@]host[:port][?transport=proto]`
+/// where `scheme` is `stun`, `stuns`, `turn` or `turns` (RFC 7064 and
+/// RFC 7065). Credentials are required by TURN servers.
+pub fn parse_ice_uri(uri: &str) -> Result<RTCIceServer, Error> {
+    let invalid = |reason: &str, cause: Option<webrtc::ice::Error>| {
+        Error::new(
+            ErrorType::InputOutput(IoError::ParsingError),
+            cause.map(|cause| Box::new(cause) as _),
+            Some(format!("ICE URI is invalid: {reason}.")),
+        )
+    };
+
+    let (scheme, rest) = uri
+        .trim()
+        .split_once(':')
+        .ok_or_else(|| invalid("missing scheme", None))?;
+
+    let (credentials, address) = match rest.rsplit_once('@') {
+        Some((credentials, address)) => {
+            let (username, credential) =
+                credentials.split_once(':').ok_or_else(|| {
+                    invalid("expected `username:credential` before `@`", None)
+                })?;
+
+            if username.is_empty() || credential.is_empty() {
+                return Err(invalid("username or credential is empty", None));
+            }
+
+            (Some((username, credential)), address)
+        },
+        None => (None, rest),
+    };
+
+    let url = format!("{scheme}:{address}");
+    let parsed = Url::parse_url(&url)
+        .map_err(|error| invalid("malformed URI", Some(error)))?;
+
+    let is_turn = matches!(parsed.scheme, SchemeType::Turn | SchemeType::Turns);
+
+    match credentials {
+        Some((username, credential)) => Ok(RTCIceServer {
+            urls: vec![url],
+            username: username.to_owned(),
+            credential: credential.to_owned(),
+            credential_type: RTCIceCredentialType::Password,
+        }),
+        None if is_turn => Err(invalid("TURN requires credentials", None)),
+        None => Ok(RTCIceServer {
+            urls: vec![url],
+            ..Default::default()
+        }),
+    }
+}
@@ -7,9 +7,11 @@
 //! use a password, while another may let you use any login you like.
 
 use crate::error::{Error, ErrorType, IoError, TokenError};
+use jsonwebtoken::errors::ErrorKind;
 use jsonwebtoken::{
     decode, encode, DecodingKey, EncodingKey, Header, Validation,
 };
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::ops::Add;
@@ -74,6 +76,12 @@ impl Claims {
         self
     }
 
+    /// Set recipient of the token.
+    pub fn audience(mut self, audience: String) -> Self {
+        self.audience = Some(audience);
+        self
+    }
+
     /// Set emitter of the token.
     pub fn issuer(mut self, issuer: String) -> Self {
         self.issuer = Some(issuer);
@@ -100,97 +108,126 @@ pub enum Key<P: AsRef<Path>> {
     Path(P),
     /// Extract key directly from a string.
     Text(String),
+    /// Shared secret, used by HMAC algorithms (`HS256`, `HS384`, `HS512`).
+    Secret(Vec<u8>),
+}
+
+impl<P: AsRef<Path>> Key<P> {
+    /// Get raw content of the key.
+    fn read(self) -> Result<Vec<u8>, Error> {
+        match self {
+            Key::Path(path) => fs::read(path).map_err(|error| {
+                Error::new(
+                    ErrorType::InputOutput(IoError::ReadingError),
+                    Some(Box::new(error)),
+                    Some("while opening file".to_owned()),
+                )
+            }),
+            Key::Text(str) => Ok(str.into_bytes()),
+            Key::Secret(secret) => Ok(secret),
+        }
+    }
+
+    fn is_secret(&self) -> bool {
+        matches!(self, Key::Secret(_))
+    }
 }
 
 /// Manage JWT.
-/// Only supports asymmetric encryption.
+///
+/// Supports RSA, ECDSA and EdDSA keys, as well as shared secrets
+/// ([`Key::Secret`]).
 #[allow(missing_debug_implementations)]
 pub struct TokenManager {
     private_key: Option<EncodingKey>,
     public_key: DecodingKey,
     algorithm: Algorithm,
+    audience: Option<String>,
+    issuer: Option<String>,
+    leeway: Duration,
 }
 
 impl TokenManager {
     /// Create a new [`TokenManager`].
+    ///
+    /// Algorithm is [`Algorithm::HS256`] when `public_key` is a
+    /// [`Key::Secret`], [`Algorithm::RS256`] otherwise. Use
+    /// [`TokenManager::with_algorithm`] for other key types.
     pub fn new<P: AsRef<Path>>(
         private_key: Option<Key<P>>,
         public_key: Key<P>,
     ) -> Result<Self, Error> {
-        let private_key = if let Some(private_key) = private_key {
-            match private_key {
-                Key::Path(path) => {
-                    let bytes = fs::read(path).map_err(|error| {
-                        Error::new(
-                            ErrorType::InputOutput(IoError::ReadingError),
-                            Some(Box::new(error)),
-                            Some("while opening file".to_owned()),
-                        )
-                    })?;
-
-                    Some(EncodingKey::from_rsa_pem(&bytes).map_err(
-                        |error| {
-                            Error::new(
-                                ErrorType::InputOutput(IoError::ReadingError),
-                                Some(Box::new(error)),
-                                Some("decoding rsa key".to_owned()),
-                            )
-                        },
-                    )?)
-                },
-                Key::Text(str) => Some(
-                    EncodingKey::from_rsa_pem(str.to_string().as_bytes())
-                        .map_err(|error| {
-                            Error::new(
-                                ErrorType::InputOutput(IoError::ReadingError),
-                                Some(Box::new(error)),
-                                Some("decoding rsa key".to_owned()),
-                            )
-                        })?,
-                ),
-            }
+        let algorithm = if public_key.is_secret() {
+            Algorithm::HS256
         } else {
-            None
+            Algorithm::RS256
         };
 
-        let public_key = match public_key {
-            Key::Path(path) => {
-                let bytes = fs::read(path).map_err(|error| {
-                    Error::new(
-                        ErrorType::InputOutput(IoError::ReadingError),
-                        Some(Box::new(error)),
-                        Some("while opening file".to_owned()),
-                    )
-                })?;
-
-                DecodingKey::from_rsa_pem(&bytes).map_err(|error| {
-                    Error::new(
-                        ErrorType::InputOutput(IoError::ReadingError),
-                        Some(Box::new(error)),
-                        Some("decoding rsa key".to_owned()),
-                    )
-                })?
-            },
-            Key::Text(str) => DecodingKey::from_rsa_pem(
-                str.to_string().as_bytes(),
+        TokenManager::with_algorithm(algorithm, private_key, public_key)
+    }
+
+    /// Create a new [`TokenManager`], loading keys as `algorithm` expects.
+    ///
+    /// HMAC algorithms expect a shared secret, others expect PEM keys.
+    pub fn with_algorithm<P: AsRef<Path>>(
+        algorithm: Algorithm,
+        private_key: Option<Key<P>>,
+        public_key: Key<P>,
+    ) -> Result<Self, Error> {
+        let decoding_error = |error: jsonwebtoken::errors::Error| {
+            Error::new(
+                ErrorType::InputOutput(IoError::ReadingError),
+                Some(Box::new(error)),
+                Some(format!("decoding {algorithm:?} key")),
             )
-            .map_err(|error| {
-                Error::new(
-                    ErrorType::InputOutput(IoError::ReadingError),
-                    Some(Box::new(error)),
-                    Some("decoding rsa key".to_owned()),
-                )
-            })?,
         };
 
+        let private_key = match private_key {
+            Some(key) => {
+                let bytes = key.read()?;
+                let key = match algorithm {
+                    Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                        Ok(EncodingKey::from_secret(&bytes))
+                    },
+                    Algorithm::ES256 | Algorithm::ES384 => {
+                        EncodingKey::from_ec_pem(&bytes)
+                    },
+                    Algorithm::EdDSA => EncodingKey::from_ed_pem(&bytes),
+                    _ => EncodingKey::from_rsa_pem(&bytes),
+                };
+
+                Some(key.map_err(decoding_error)?)
+            },
+            None => None,
+        };
+
+        let bytes = public_key.read()?;
+        let public_key = match algorithm {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                Ok(DecodingKey::from_secret(&bytes))
+            },
+            Algorithm::ES256 | Algorithm::ES384 => {
+                DecodingKey::from_ec_pem(&bytes)
+            },
+            Algorithm::EdDSA => DecodingKey::from_ed_pem(&bytes),
+            _ => DecodingKey::from_rsa_pem(&bytes),
+        }
+        .map_err(decoding_error)?;
+
         Ok(TokenManager {
             private_key,
             public_key,
-            algorithm: Algorithm::RS256,
+            algorithm,
+            audience: None,
+            issuer: None,
+            leeway: Duration::from_secs(60),
         })
     }
 
     /// Update JWT algorithm.
+    ///
+    /// It must use the same kind of keys as the ones given to
+    /// [`TokenManager::with_algorithm`], e.g. `RS256` to `PS512`.
     pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
         self.algorithm = algorithm;
         self
@@ -198,8 +235,12 @@ impl TokenManager {
 
     /// Create a new custom JWT.
     ///
-    /// `private_key` must be set.
-    pub fn create_token(&self, claims: &Claims) -> Result<String, Error> {
+    /// `private_key` must be set. `claims` is usually [`Claims`], or a type
+    /// extending it with server-specific claims.
+    pub fn create_token<T: Serialize>(
+        &self,
+        claims: &T,
+    ) -> Result<String, Error> {
         if let Some(private_key) = &self.private_key {
             let token =
                 encode(&Header::new(self.algorithm), claims, private_key)
@@ -217,60 +258,177 @@ impl TokenManager {
         }
     }
 
+    /// Only accept tokens whose `aud` claim contains `audience`.
+    ///
+    /// Tokens without `aud` claim are rejected. Without expected audience,
+    /// `aud` claim is not checked.
+    pub fn expect_audience<T: AsRef<str>>(mut self, audience: T) -> Self {
+        self.audience = Some(audience.as_ref().to_owned());
+        self
+    }
+
+    /// Only accept tokens issued by `issuer`.
+    ///
+    /// Tokens without `iss` claim are rejected.
+    pub fn expect_issuer<T: AsRef<str>>(mut self, issuer: T) -> Self {
+        self.issuer = Some(issuer.as_ref().to_owned());
+        self
+    }
+
+    /// Tolerate clock skew between client and server when checking
+    /// `exp` and `nbf` claims.
+    ///
+    /// Default is 60 seconds.
+    pub fn leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Seconds left before `token` expires, negative once it is expired.
+    ///
+    /// Signature is checked, but not `exp` and `nbf` claims. Returns
+    /// [`None`] if `token` is invalid or has no `exp` claim.
+    pub fn seconds_until_expiry(&self, token: &str) -> Option<i64> {
+        let mut validation = self.validation();
+        validation.validate_exp = false;
+        validation.validate_nbf = false;
+        validation.required_spec_claims.remove("exp");
+
+        let expire_at = decode::<Claims>(token, &self.public_key, &validation)
+            .ok()?
+            .claims
+            .expire_at?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        Some(i64::try_from(expire_at).ok()? - i64::try_from(now).ok()?)
+    }
+
+    /// Rules checked on every decoded JWT.
+    fn validation(&self) -> Validation {
+        let mut validation = Validation::new(self.algorithm);
+        validation.leeway = self.leeway.as_secs();
+        validation.validate_nbf = true;
+        match &self.audience {
+            Some(audience) => {
+                validation.set_audience(&[audience]);
+                validation.required_spec_claims.insert("aud".to_owned());
+            },
+            // Any audience is accepted when none is expected.
+            None => validation.validate_aud = false,
+        }
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+            validation.required_spec_claims.insert("iss".to_owned());
+        }
+
+        validation
+    }
+
     /// Decode and check a JWT.
     pub fn decode(&self, token: &str) -> Result<Claims, Error> {
-        let claims: Claims =
-            decode(token, &self.public_key, &Validation::new(self.algorithm))
-                .map_err(|error| {
-                    Error::new(
-                        ErrorType::Token(TokenError::Fail),
-                        Some(Box::new(error)),
-                        Some("decoding jwt".to_owned()),
-                    )
-                })?
-                .claims;
-
-        if claims
-            .expire_at
-            .and_then(|expire_at| {
-                (expire_at
-                    < std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_secs())
-                .then_some(..)
-            })
-            .is_some()
-        {
-            return Err(Error::new(
-                ErrorType::Token(TokenError::Expired),
-                None,
-                Some("token is expired".to_owned()),
-            ));
-        }
+        self.decode_as(token)
+    }
 
-        if claims
-            .not_before
-            .and_then(|not_before| {
-                (not_before
-                    > std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_secs())
-                .then_some(..)
-            })
-            .is_some()
-        {
-            return Err(Error::new(
-                ErrorType::Token(TokenError::Early),
-                None,
-                Some(
-                    "`not_before` claim is older than actual timestamp"
-                        .to_owned(),
-                ),
-            ));
-        }
+    /// Decode and check a JWT, extracting its claims as `T`.
+    ///
+    /// Useful to read claims specific to a discovery server, e.g. `scope`.
+    /// `exp`, `nbf`, `aud` and `iss` claims are checked even if `T` does not
+    /// contain them.
+    pub fn decode_as<T: DeserializeOwned>(
+        &self,
+        token: &str,
+    ) -> Result<T, Error> {
+        let claims = decode(token, &self.public_key, &self.validation())
+            .map_err(|error| {
+                let etype = match error.kind() {
+                    ErrorKind::ExpiredSignature => TokenError::Expired,
+                    ErrorKind::ImmatureSignature => TokenError::Early,
+                    ErrorKind::InvalidAudience => TokenError::Audience,
+                    ErrorKind::InvalidIssuer => TokenError::Issuer,
+                    ErrorKind::MissingRequiredClaim(claim)
+                        if claim == "aud" =>
+                    {
+                        TokenError::Audience
+                    },
+                    ErrorKind::MissingRequiredClaim(claim)
+                        if claim == "iss" =>
+                    {
+                        TokenError::Issuer
+                    },
+                    _ => TokenError::Fail,
+                };
+
+                Error::new(
+                    ErrorType::Token(etype),
+                    Some(Box::new(error)),
+                    Some("decoding jwt".to_owned()),
+                )
+            })?
+            .claims;
 
         Ok(claims)
     }
 }
+
+/// Generate a new key pair for `algorithm`.
+///
+/// Returns `(private_key, public_key)`, PEM-encoded as PKCS#8 and SPKI, which
+/// can be given to [`TokenManager::with_algorithm`] with [`Key::Text`].
+/// RSA-based algorithms use 2048-bit keys. Only RSA and
+/// [`Algorithm::ES256`] are supported.
+#[cfg(feature = "keygen")]
+pub fn generate_keypair(
+    algorithm: Algorithm,
+) -> Result<(String, String), Error> {
+    use rsa::pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding};
+    use rsa::rand_core::OsRng;
+
+    let fail = |error: Box<dyn std::error::Error + Send + Sync>| {
+        Error::new(
+            ErrorType::Token(TokenError::Fail),
+            Some(error),
+            Some("generating key pair".to_owned()),
+        )
+    };
+
+    match algorithm {
+        Algorithm::RS256
+        | Algorithm::RS384
+        | Algorithm::RS512
+        | Algorithm::PS256
+        | Algorithm::PS384
+        | Algorithm::PS512 => {
+            let private_key = rsa::RsaPrivateKey::new(&mut OsRng, 2048)
+                .map_err(|error| fail(Box::new(error)))?;
+            let private_pem = private_key
+                .to_pkcs8_pem(LineEnding::LF)
+                .map_err(|error| fail(Box::new(error)))?;
+            let public_pem = private_key
+                .to_public_key()
+                .to_public_key_pem(LineEnding::LF)
+                .map_err(|error| fail(Box::new(error)))?;
+
+            Ok((private_pem.to_string(), public_pem))
+        },
+        Algorithm::ES256 => {
+            let private_key = p256::SecretKey::random(&mut OsRng);
+            let private_pem = private_key
+                .to_pkcs8_pem(LineEnding::LF)
+                .map_err(|error| fail(Box::new(error)))?;
+            let public_pem = private_key
+                .public_key()
+                .to_public_key_pem(LineEnding::LF)
+                .map_err(|error| fail(Box::new(error)))?;
+
+            Ok((private_pem.to_string(), public_pem))
+        },
+        _ => Err(Error::new(
+            ErrorType::Token(TokenError::Fail),
+            None,
+            Some(format!("cannot generate a key pair for {algorithm:?}")),
+        )),
+    }
+}
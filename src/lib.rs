@@ -8,8 +8,12 @@
 )]
 //! Manage communication between Turms and client.
 
+pub mod auth;
 pub mod error;
 mod future;
+pub mod ice;
 pub mod jwt;
+pub mod log;
 pub mod models;
+pub mod tls;
 pub mod websocket;
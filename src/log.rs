@@ -0,0 +1,83 @@
+//! Keep sensitive data out of logs.
+//!
+//! Turms is privacy-focused: content exchanged with the discovery server can
+//! reveal who talks to whom, and signaling data reveals IP addresses.
+
+use std::fmt;
+
+/// Controls which sensitive data may be written to logs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Redaction {
+    messages: bool,
+}
+
+impl Default for Redaction {
+    /// Everything is redacted in release builds, nothing in debug builds.
+    fn default() -> Self {
+        let redact = !cfg!(debug_assertions);
+
+        Redaction { messages: redact }
+    }
+}
+
+impl Redaction {
+    /// Log everything, useful while debugging.
+    pub fn none() -> Self {
+        Redaction { messages: false }
+    }
+
+    /// Never log sensitive data.
+    pub fn all() -> Self {
+        Redaction { messages: true }
+    }
+
+    /// Hide content of messages received from the discovery server.
+    pub fn messages(mut self, redact: bool) -> Self {
+        self.messages = redact;
+        self
+    }
+
+    /// Whether content of messages received from the discovery server is
+    /// hidden.
+    pub fn redacts_messages(&self) -> bool {
+        self.messages
+    }
+}
+
+/// Sensitive text, displaying only its length when redacted.
+///
+/// Works with any logger, e.g. `info!("SDP: {}", Sensitive::new(true, sdp))`.
+#[derive(Clone, Copy, Debug)]
+pub struct Sensitive<'a> {
+    redact: bool,
+    value: &'a str,
+}
+
+impl<'a> Sensitive<'a> {
+    /// Wrap `value`, hidden if `redact` is `true`.
+    pub fn new(redact: bool, value: &'a str) -> Self {
+        Sensitive { redact, value }
+    }
+}
+
+impl fmt::Display for Sensitive<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.redact {
+            write!(f, "<redacted, {} bytes>", self.value.len())
+        } else {
+            write!(f, "{:?}", self.value)
+        }
+    }
+}
+
+/// Print a sensitive value, or only its length when `$redact` is `true`.
+macro_rules! log_sensitive {
+    ($redact:expr, $label:literal, $value:expr) => {
+        println!(
+            concat!($label, ": {}"),
+            $crate::log::Sensitive::new($redact, &$value)
+        )
+    };
+}
+
+pub(crate) use log_sensitive;
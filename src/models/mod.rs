@@ -5,11 +5,14 @@ pub mod response;
 
 use serde::de::Deserialize;
 
-/// Convert a [`String`] into [`u64`].
-pub(crate) fn string_to_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+/// Convert an optional [`String`] into [`u64`].
+pub(crate) fn option_string_to_u64<'de, D>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    let s = String::deserialize(deserializer)?;
-    s.parse::<u64>().map_err(serde::de::Error::custom)
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| s.parse::<u64>().map_err(serde::de::Error::custom))
+        .transpose()
 }
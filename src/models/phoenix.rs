@@ -1,39 +1,64 @@
 //! Phoenix message model.
 
 use crate::error::{Error, ErrorType, IoError};
-use crate::models::string_to_u64;
+use crate::models::option_string_to_u64;
+use serde::de::DeserializeOwned;
 use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
 
 /// Enumerate all events usable with Turms.
-#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Event {
     /// Join a Phoenix channel.
     #[serde(rename = "phx_join")]
     #[default]
     Join,
+    /// Leave a Phoenix channel.
+    #[serde(rename = "phx_leave")]
+    Leave,
     /// I'm still alive!
     Heartbeat,
     /// Only send by server.
     /// Sent after joining, it enumerates every messages sent by relations while offline.
     #[serde(rename = "pending_messages")]
     UnreadMessages,
+    /// Only send by server.
+    /// Reply to a message, sharing its reference.
+    #[serde(rename = "phx_reply")]
+    Reply,
+    /// Only send by server.
+    /// Channel crashed on server side.
+    #[serde(rename = "phx_error")]
+    Error,
+    /// Only send by server.
+    /// Channel has been closed.
+    #[serde(rename = "phx_close")]
+    Close,
+    /// Any other event, e.g. defined by discovery server.
+    #[serde(untagged)]
+    Custom(String),
 }
 
-/// Message to send towards WebSocket.
-#[derive(Debug, Default, Deserialize)]
+/// Message exchanged with WebSocket.
+#[derive(Clone, Debug, Default, Deserialize)]
 pub struct Message<D>
 where
     D: Serialize,
 {
+    /// Channel the message belongs to. Empty for the lobby.
+    #[serde(default)]
+    pub topic: String,
     /// What happened?
-    event: Event,
+    pub event: Event,
     /// Additional data in message.
-    payload: D,
+    pub payload: D,
     /// Reference of websocket message.
-    #[serde(deserialize_with = "string_to_u64")]
-    reference: u64,
+    ///
+    /// Messages pushed by server have no reference.
+    #[serde(rename = "ref", default)]
+    #[serde(deserialize_with = "option_string_to_u64")]
+    pub reference: Option<u64>,
 }
 
 impl<D> Serialize for Message<D>
@@ -44,17 +69,21 @@ where
     where
         S: Serializer,
     {
-        let topic = if self.event == Event::Heartbeat {
+        // Heartbeats always belong to `phoenix` topic.
+        let topic = if self.event == Event::Heartbeat && self.topic.is_empty() {
             "phoenix"
         } else {
-            ""
+            &self.topic
         };
 
         let mut state = serializer.serialize_struct("Message", 4)?;
         state.serialize_field("topic", topic)?;
         state.serialize_field("event", &self.event)?;
         state.serialize_field("payload", &self.payload)?;
-        state.serialize_field("ref", &self.reference.to_string())?;
+        state.serialize_field(
+            "ref",
+            &self.reference.map(|reference| reference.to_string()),
+        )?;
         state.end()
     }
 }
@@ -63,6 +92,12 @@ impl<D> Message<D>
 where
     D: Serialize,
 {
+    /// Update `topic` field on [`Message`].
+    pub fn topic<T: Into<String>>(mut self, topic: T) -> Self {
+        self.topic = topic.into();
+        self
+    }
+
     /// Update `event` field on [`Message`].
     pub fn event(mut self, event: Event) -> Self {
         self.event = event;
@@ -71,7 +106,13 @@ where
 
     /// Update `reference` field on [`Message`].
     pub fn r#ref(mut self, reference: u64) -> Self {
-        self.reference = reference;
+        self.reference = Some(reference);
+        self
+    }
+
+    /// Update `payload` field on [`Message`].
+    pub fn payload(mut self, payload: D) -> Self {
+        self.payload = payload;
         self
     }
 
@@ -86,3 +127,19 @@ where
         })
     }
 }
+
+impl<D> Message<D>
+where
+    D: Serialize + DeserializeOwned,
+{
+    /// Parse a [`Message`] from its JSON structure.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|error| {
+            Error::new(
+                ErrorType::InputOutput(IoError::ParsingError),
+                Some(Box::new(error)),
+                Some("Message cannot be parsed.".to_owned()),
+            )
+        })
+    }
+}
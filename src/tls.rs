@@ -0,0 +1,411 @@
+//! TLS settings used to reach the discovery server.
+//!
+//! # Certificate pinning
+//!
+//! A pin is the SHA-256 digest of a certificate's SubjectPublicKeyInfo,
+//! encoded in base64 and prefixed with `sha256/`, as in HPKP:
+//!
+//! ```sh
+//! openssl x509 -in cert.pem -pubkey -noout \
+//!   | openssl pkey -pubin -outform der \
+//!   | openssl dgst -sha256 -binary | base64
+//! ```
+//!
+//! A connection is accepted when the certificate chain is trusted **and** any
+//! certificate of the chain matches one of the pins. Since a key rotation on
+//! the server breaks pinned clients, always pin a backup key (or the key of
+//! the issuing CA) alongside the current one.
+//!
+//! # Private deployments
+//!
+//! Servers using a private certificate authority are trusted once its
+//! certificate is added with [`TlsOptions::root_cert_pem`], alongside the
+//! WebPKI roots. Servers requiring mutual TLS get the certificate set with
+//! [`TlsOptions::client_identity_pem`].
+
+use crate::error::{Error, ErrorType, IoError};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::digest::{digest, SHA256};
+use rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::ring as provider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+
+use std::sync::Arc;
+
+/// Prefix of every pin.
+const PIN_PREFIX: &str = "sha256/";
+
+/// TLS options applied to both HTTP and WebSocket connections.
+#[derive(Clone, Debug, Default)]
+pub struct TlsOptions {
+    pins: Vec<[u8; 32]>,
+    root_certs: Vec<CertificateDer<'static>>,
+    client_identity: Option<ClientIdentity>,
+}
+
+/// Certificate chain and private key presented to the server.
+struct ClientIdentity {
+    certs: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+}
+
+impl Clone for ClientIdentity {
+    fn clone(&self) -> Self {
+        ClientIdentity {
+            certs: self.certs.clone(),
+            key: self.key.clone_key(),
+        }
+    }
+}
+
+impl std::fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientIdentity")
+            .field("certs", &self.certs)
+            .field("key", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Parse every PEM certificate of `pem`.
+fn parse_certs(pem: &[u8]) -> Result<Vec<CertificateDer<'static>>, Error> {
+    let certs = CertificateDer::pem_slice_iter(pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| {
+            Error::new(
+                ErrorType::InputOutput(IoError::ParsingError),
+                Some(Box::new(error)),
+                Some("Invalid PEM certificate.".to_owned()),
+            )
+        })?;
+
+    if certs.is_empty() {
+        return Err(Error::new(
+            ErrorType::InputOutput(IoError::ParsingError),
+            None,
+            Some("No PEM certificate found.".to_owned()),
+        ));
+    }
+
+    Ok(certs)
+}
+
+impl TlsOptions {
+    /// Only accept certificate chains containing this public key.
+    ///
+    /// See [module documentation](self) for the pin format.
+    pub fn pin<T: AsRef<str>>(mut self, pin: T) -> Result<Self, Error> {
+        let invalid = |cause: Option<base64::DecodeError>| {
+            Error::new(
+                ErrorType::InputOutput(IoError::ParsingError),
+                cause.map(|cause| Box::new(cause) as _),
+                Some(format!(
+                    "pin must be `{PIN_PREFIX}` followed by a base64 SHA-256 \
+                     digest."
+                )),
+            )
+        };
+
+        let encoded = pin
+            .as_ref()
+            .strip_prefix(PIN_PREFIX)
+            .ok_or_else(|| invalid(None))?;
+        let digest = STANDARD
+            .decode(encoded)
+            .map_err(|error| invalid(Some(error)))?
+            .try_into()
+            .map_err(|_| invalid(None))?;
+
+        self.pins.push(digest);
+        Ok(self)
+    }
+
+    /// Also trust certificate authorities of `pem`, e.g. a private CA.
+    ///
+    /// WebPKI roots stay trusted.
+    pub fn root_cert_pem<T: AsRef<[u8]>>(
+        mut self,
+        pem: T,
+    ) -> Result<Self, Error> {
+        let certs = parse_certs(pem.as_ref())?;
+
+        // Reject invalid certificates now rather than when connecting.
+        let mut roots = RootCertStore::empty();
+        for cert in &certs {
+            roots.add(cert.clone()).map_err(|error| {
+                Error::new(
+                    ErrorType::InputOutput(IoError::ParsingError),
+                    Some(Box::new(error)),
+                    Some("Invalid root certificate.".to_owned()),
+                )
+            })?;
+        }
+
+        self.root_certs.extend(certs);
+        Ok(self)
+    }
+
+    /// Authenticate to the server with a client certificate (mutual TLS).
+    ///
+    /// `certs` is the PEM certificate chain, starting with the client
+    /// certificate, and `key` its PEM private key.
+    pub fn client_identity_pem<T: AsRef<[u8]>>(
+        mut self,
+        certs: T,
+        key: T,
+    ) -> Result<Self, Error> {
+        let certs = parse_certs(certs.as_ref())?;
+        let key =
+            PrivateKeyDer::from_pem_slice(key.as_ref()).map_err(|error| {
+                Error::new(
+                    ErrorType::InputOutput(IoError::ParsingError),
+                    Some(Box::new(error)),
+                    Some("Invalid PEM private key.".to_owned()),
+                )
+            })?;
+
+        self.client_identity = Some(ClientIdentity { certs, key });
+        Ok(self)
+    }
+
+    /// Build a [`ClientConfig`], or [`None`] to keep default behaviour.
+    pub(crate) fn client_config(
+        &self,
+    ) -> Result<Option<Arc<ClientConfig>>, Error> {
+        if self.pins.is_empty()
+            && self.root_certs.is_empty()
+            && self.client_identity.is_none()
+        {
+            return Ok(None);
+        }
+
+        let tls_error = |error: rustls::Error| {
+            Error::new(
+                ErrorType::InputOutput(IoError::ConnectionError),
+                Some(Box::new(error)),
+                Some("Invalid TLS configuration.".to_owned()),
+            )
+        };
+
+        let provider = Arc::new(provider::default_provider());
+        let mut roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        for cert in &self.root_certs {
+            roots.add(cert.clone()).map_err(tls_error)?;
+        }
+        let inner = WebPkiServerVerifier::builder_with_provider(
+            Arc::new(roots),
+            Arc::clone(&provider),
+        )
+        .build()
+        .map_err(|error| {
+            tls_error(rustls::Error::General(error.to_string()))
+        })?;
+
+        let builder = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?;
+        let builder = if self.pins.is_empty() {
+            builder.with_webpki_verifier(inner)
+        } else {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(PinnedVerifier {
+                    inner,
+                    pins: self.pins.clone(),
+                }))
+        };
+
+        let config = match self.client_identity {
+            Some(ref identity) => builder
+                .with_client_auth_cert(
+                    identity.certs.clone(),
+                    identity.key.clone_key(),
+                )
+                .map_err(tls_error)?,
+            None => builder.with_no_client_auth(),
+        };
+
+        Ok(Some(Arc::new(config)))
+    }
+}
+
+/// Run WebPKI verification, then check certificate pins.
+#[derive(Debug)]
+struct PinnedVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    pins: Vec<[u8; 32]>,
+}
+
+impl PinnedVerifier {
+    fn is_pinned(&self, certificate: &CertificateDer<'_>) -> bool {
+        x509_parser::parse_x509_certificate(certificate)
+            .map(|(_, certificate)| {
+                let spki = digest(&SHA256, certificate.public_key().raw);
+                self.pins.iter().any(|pin| pin == spki.as_ref())
+            })
+            .unwrap_or(false)
+    }
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+
+        if std::iter::once(end_entity)
+            .chain(intermediates)
+            .any(|certificate| self.is_pinned(certificate))
+        {
+            Ok(verified)
+        } else {
+            Err(rustls::Error::General(
+                "server certificate does not match any pin".to_owned(),
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustls::{
+        ClientConnection, ServerConfig, ServerConnection, StreamOwned,
+    };
+    use std::net::{TcpListener, TcpStream};
+
+    /// Pins computed with `openssl`, as shown in module documentation.
+    const CLIENT_PIN: &str =
+        "sha256/zwe9SEWW/7BzkHzgtg/AP6lE68Y0i9eAtA9IlpKxEmY=";
+    const SERVER_PIN: &str =
+        "sha256/G0PxqSPINxRgvE3YhPscoqEc4DlxReheVog5xTDGDv0=";
+
+    fn read_cert(path: &str) -> CertificateDer<'static> {
+        let pem = std::fs::read(path).unwrap();
+        parse_certs(&pem).unwrap().remove(0)
+    }
+
+    #[test]
+    fn assert_is_pinned() {
+        let certificate = read_cert("./tests/client.pem");
+        let verifier = |options: TlsOptions| PinnedVerifier {
+            inner: WebPkiServerVerifier::builder_with_provider(
+                Arc::new(RootCertStore {
+                    roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+                }),
+                Arc::new(provider::default_provider()),
+            )
+            .build()
+            .unwrap(),
+            pins: options.pins,
+        };
+
+        let pinned = verifier(TlsOptions::default().pin(CLIENT_PIN).unwrap());
+        assert!(pinned.is_pinned(&certificate));
+
+        let other = verifier(TlsOptions::default().pin(SERVER_PIN).unwrap());
+        assert!(!other.is_pinned(&certificate));
+    }
+
+    /// Run a TLS handshake against a local server, trusting the test CA.
+    fn handshake(options: TlsOptions) -> Result<(), std::io::Error> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let key = std::fs::read("./tests/server.key").unwrap();
+        let server_config = ServerConfig::builder_with_provider(Arc::new(
+            provider::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![read_cert("./tests/server.pem")],
+            PrivateKeyDer::from_pem_slice(&key).unwrap(),
+        )
+        .unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let connection =
+                ServerConnection::new(Arc::new(server_config)).unwrap();
+            let mut stream = StreamOwned::new(connection, stream);
+            // Failures are reported by the client.
+            let _ = stream.conn.complete_io(&mut stream.sock);
+        });
+
+        let ca = std::fs::read("./tests/ca.pem").unwrap();
+        let config = options
+            .root_cert_pem(ca)
+            .unwrap()
+            .client_config()
+            .unwrap()
+            .unwrap();
+        let connection = ClientConnection::new(
+            config,
+            ServerName::try_from("localhost").unwrap(),
+        )
+        .unwrap();
+        let mut stream =
+            StreamOwned::new(connection, TcpStream::connect(address).unwrap());
+        let result = stream.conn.complete_io(&mut stream.sock).map(|_| ());
+
+        drop(stream);
+        server.join().unwrap();
+        result
+    }
+
+    #[test]
+    fn assert_pinned_handshake() {
+        assert!(handshake(TlsOptions::default()).is_ok());
+        assert!(
+            handshake(TlsOptions::default().pin(SERVER_PIN).unwrap()).is_ok()
+        );
+
+        let error = handshake(TlsOptions::default().pin(CLIENT_PIN).unwrap())
+            .unwrap_err();
+        assert!(error.to_string().contains("does not match any pin"));
+    }
+}
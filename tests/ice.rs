@@ -0,0 +1,30 @@
+use libturms::ice::*;
+
+#[test]
+fn assert_parse_stun_uri() {
+    let server = parse_ice_uri("stun:stun.l.google.com:19302").unwrap();
+
+    assert_eq!(server.urls, vec!["stun:stun.l.google.com:19302"]);
+    assert!(server.username.is_empty());
+}
+
+#[test]
+fn assert_parse_turn_uri() {
+    let server =
+        parse_ice_uri("turn:user:s3cret@turn.domain.tld:3478?transport=tcp")
+            .unwrap();
+
+    assert_eq!(server.urls, vec!["turn:turn.domain.tld:3478?transport=tcp"]);
+    assert_eq!(server.username, "user");
+    assert_eq!(server.credential, "s3cret");
+}
+
+#[test]
+fn assert_reject_malformed_uri() {
+    assert!(parse_ice_uri("turn.domain.tld").is_err());
+    assert!(parse_ice_uri("turn:turn.domain.tld").is_err());
+    assert!(parse_ice_uri("turn:user@turn.domain.tld").is_err());
+    assert!(parse_ice_uri("turn:user:pass@host?transport=sctp").is_err());
+    assert!(parse_ice_uri("stun:host?transport=udp").is_err());
+    assert!(parse_ice_uri("http://host").is_err());
+}
@@ -1,6 +1,18 @@
+use libturms::error::{ErrorType, TokenError};
 use libturms::jwt::*;
 use regex_lite::Regex;
 
+/// Manager signing and checking tokens with a shared secret.
+fn hmac_manager() -> TokenManager {
+    let secret = b"turms-shared-secret".to_vec();
+
+    TokenManager::new::<&str>(
+        Some(Key::Secret(secret.clone())),
+        Key::Secret(secret),
+    )
+    .unwrap()
+}
+
 #[test]
 fn assert_create_token() {
     let manager = TokenManager::new(
@@ -19,3 +31,209 @@ fn assert_create_token() {
         .captures(&token)
         .is_some());
 }
+
+#[cfg(feature = "keygen")]
+#[test]
+fn assert_generated_keypair() {
+    let (private_key, public_key) = generate_keypair(Algorithm::RS256).unwrap();
+    let manager = TokenManager::new::<&str>(
+        Some(Key::Text(private_key)),
+        Key::Text(public_key),
+    )
+    .unwrap();
+
+    let claims = Claims::new("user1".into())
+        .expire_after(std::time::Duration::from_secs(60));
+    let token = manager.create_token(&claims).unwrap();
+
+    assert_eq!(manager.decode(&token).unwrap().subject, "user1");
+
+    let (private_key, public_key) = generate_keypair(Algorithm::ES256).unwrap();
+    let manager = TokenManager::with_algorithm::<&str>(
+        Algorithm::ES256,
+        Some(Key::Text(private_key)),
+        Key::Text(public_key),
+    )
+    .unwrap();
+    let token = manager.create_token(&claims).unwrap();
+
+    assert_eq!(manager.decode(&token).unwrap().subject, "user1");
+    assert!(generate_keypair(Algorithm::HS256).is_err());
+}
+
+#[test]
+fn assert_hmac_token() {
+    let manager = hmac_manager().algorithm(Algorithm::HS384);
+
+    let claims = Claims::new("user1".into())
+        .expire_after(std::time::Duration::from_secs(60));
+    let token = manager.create_token(&claims).unwrap();
+
+    assert!(token.starts_with("eyJ0eXAiOiJKV1QiLCJhbGciOiJIUzM4NCJ9."));
+    assert_eq!(manager.decode(&token).unwrap().subject, "user1");
+
+    let other = TokenManager::new::<&str>(None, Key::Secret(b"other".to_vec()))
+        .unwrap()
+        .algorithm(Algorithm::HS384);
+
+    assert!(other.decode(&token).is_err());
+}
+
+#[test]
+fn assert_eddsa_token() {
+    let manager = TokenManager::with_algorithm(
+        Algorithm::EdDSA,
+        Some(Key::Path("./tests/ed25519.key")),
+        Key::Path("./tests/ed25519.pub"),
+    )
+    .unwrap();
+
+    let claims = Claims::new("user1".into())
+        .expire_after(std::time::Duration::from_secs(60));
+    let token = manager.create_token(&claims).unwrap();
+
+    assert_eq!(manager.decode(&token).unwrap().subject, "user1");
+
+    // An Ed25519 key is not an RSA key.
+    assert!(TokenManager::new(None, Key::Path("./tests/ed25519.pub")).is_err());
+}
+
+#[test]
+fn assert_audience_and_issuer() {
+    let manager = hmac_manager();
+
+    let claims = Claims::new("user1".into())
+        .expire_after(std::time::Duration::from_secs(60));
+    let anonymous = manager.create_token(&claims).unwrap();
+    let token = manager
+        .create_token(
+            &claims
+                .audience("turms.example".into())
+                .issuer("discovery.example".into()),
+        )
+        .unwrap();
+
+    // Nothing expected.
+    assert!(manager.decode(&anonymous).is_ok());
+    assert!(manager.decode(&token).is_ok());
+
+    let manager = manager
+        .expect_audience("turms.example")
+        .expect_issuer("discovery.example");
+
+    assert!(manager.decode(&token).is_ok());
+
+    let manager = hmac_manager().expect_audience("turms.example");
+
+    assert!(matches!(
+        manager.decode(&anonymous).unwrap_err().etype,
+        ErrorType::Token(TokenError::Audience)
+    ));
+    assert!(matches!(
+        manager
+            .expect_audience("other.example")
+            .decode(&token)
+            .unwrap_err()
+            .etype,
+        ErrorType::Token(TokenError::Audience)
+    ));
+
+    let manager = hmac_manager().expect_issuer("discovery.example");
+
+    assert!(matches!(
+        manager.decode(&anonymous).unwrap_err().etype,
+        ErrorType::Token(TokenError::Issuer)
+    ));
+    assert!(matches!(
+        manager
+            .expect_issuer("other.example")
+            .decode(&token)
+            .unwrap_err()
+            .etype,
+        ErrorType::Token(TokenError::Issuer)
+    ));
+}
+
+#[test]
+fn assert_leeway() {
+    let manager = hmac_manager();
+
+    let mut claims = Claims::new("user1".into());
+    claims.expire_at = Some(claims.issued_at - 10);
+    let expired = manager.create_token(&claims).unwrap();
+
+    claims.expire_at = Some(claims.issued_at + 60);
+    claims.not_before = Some(claims.issued_at + 10);
+    let early = manager.create_token(&claims).unwrap();
+
+    // Within default leeway.
+    assert!(manager.decode(&expired).is_ok());
+    assert!(manager.decode(&early).is_ok());
+
+    let manager = manager.leeway(std::time::Duration::ZERO);
+
+    assert!(matches!(
+        manager.decode(&expired).unwrap_err().etype,
+        ErrorType::Token(TokenError::Expired)
+    ));
+    assert!(matches!(
+        manager.decode(&early).unwrap_err().etype,
+        ErrorType::Token(TokenError::Early)
+    ));
+}
+
+#[test]
+fn assert_seconds_until_expiry() {
+    use std::time::Duration;
+
+    let manager = hmac_manager();
+
+    let claims =
+        Claims::new("user1".into()).expire_after(Duration::from_secs(3600));
+    let token = manager.create_token(&claims).unwrap();
+    let seconds = manager.seconds_until_expiry(&token).unwrap();
+    assert!((3590..=3600).contains(&seconds));
+
+    let mut claims = Claims::new("user1".into());
+    claims.expire_at = Some(claims.issued_at - 120);
+    let token = manager.create_token(&claims).unwrap();
+    assert!(manager.seconds_until_expiry(&token).unwrap() <= -120);
+
+    let token = manager.create_token(&Claims::new("user1".into())).unwrap();
+    assert_eq!(manager.seconds_until_expiry(&token), None);
+    assert_eq!(manager.seconds_until_expiry("not.a.token"), None);
+}
+
+#[test]
+fn assert_custom_claims() {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct ScopedClaims {
+        #[serde(flatten)]
+        claims: Claims,
+        scope: String,
+    }
+
+    let manager = hmac_manager();
+
+    let token = manager
+        .create_token(&ScopedClaims {
+            claims: Claims::new("user1".into())
+                .expire_after(std::time::Duration::from_secs(60)),
+            scope: "chat:write".into(),
+        })
+        .unwrap();
+
+    let decoded: ScopedClaims = manager.decode_as(&token).unwrap();
+    assert_eq!(decoded.scope, "chat:write");
+    assert_eq!(decoded.claims.subject, "user1");
+    assert_eq!(manager.decode(&token).unwrap().subject, "user1");
+
+    // Standard claims are checked even if `T` ignores them.
+    let mut claims = Claims::new("user1".into());
+    claims.expire_at = Some(claims.issued_at - 120);
+    let token = manager.create_token(&claims).unwrap();
+
+    assert!(manager.decode_as::<serde_json::Value>(&token).is_err());
+}
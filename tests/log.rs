@@ -0,0 +1,18 @@
+use libturms::log::*;
+
+#[test]
+fn assert_redaction() {
+    let sensitive = "candidate:1 1 udp 2122260223 192.0.2.1 54400 typ host";
+
+    assert_eq!(
+        Sensitive::new(true, sensitive).to_string(),
+        format!("<redacted, {} bytes>", sensitive.len())
+    );
+    assert_eq!(
+        Sensitive::new(false, sensitive).to_string(),
+        format!("{sensitive:?}")
+    );
+
+    assert!(Redaction::all().redacts_messages());
+    assert!(!Redaction::all().messages(false).redacts_messages());
+}
@@ -0,0 +1,41 @@
+use libturms::models::phoenix::*;
+use serde_json::Value;
+
+#[test]
+fn assert_parse_push() {
+    let message = Message::<Value>::from_json(
+        r#"{"topic":"","event":"pending_messages","payload":{"messages":[]},"ref":null}"#,
+    )
+    .unwrap();
+
+    assert_eq!(message.event, Event::UnreadMessages);
+    assert_eq!(message.reference, None);
+    assert_eq!(message.payload["messages"], Value::Array(Vec::new()));
+}
+
+#[test]
+fn assert_parse_reply() {
+    let message = Message::<Value>::from_json(
+        r#"{"topic":"","event":"phx_reply","payload":{"status":"ok"},"ref":"3"}"#,
+    )
+    .unwrap();
+
+    assert_eq!(message.event, Event::Reply);
+    assert_eq!(message.reference, Some(3));
+}
+
+#[test]
+fn assert_custom_event_round_trip() {
+    let json = Message::default()
+        .event(Event::Custom("offer".into()))
+        .payload("sdp".to_owned())
+        .r#ref(7)
+        .to_json()
+        .unwrap();
+
+    let message = Message::<String>::from_json(&json).unwrap();
+
+    assert_eq!(message.event, Event::Custom("offer".into()));
+    assert_eq!(message.payload, "sdp");
+    assert_eq!(message.reference, Some(7));
+}
@@ -0,0 +1,39 @@
+use libturms::tls::*;
+
+#[test]
+fn assert_parse_pin() {
+    assert!(TlsOptions::default()
+        .pin("sha256/47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=")
+        .is_ok());
+
+    // Missing prefix.
+    assert!(TlsOptions::default()
+        .pin("47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=")
+        .is_err());
+    // Not base64.
+    assert!(TlsOptions::default().pin("sha256/not base64!").is_err());
+    // Not a SHA-256 digest.
+    assert!(TlsOptions::default().pin("sha256/AAAA").is_err());
+}
+
+#[test]
+fn assert_private_ca_and_client_identity() {
+    let cert = std::fs::read("./tests/client.pem").unwrap();
+    let key = std::fs::read("./tests/client.key").unwrap();
+
+    assert!(TlsOptions::default().root_cert_pem(&cert).is_ok());
+    assert!(TlsOptions::default()
+        .client_identity_pem(&cert, &key)
+        .is_ok());
+
+    // Not PEM.
+    assert!(TlsOptions::default().root_cert_pem(b"not a cert").is_err());
+    // Key given as certificate chain.
+    assert!(TlsOptions::default()
+        .client_identity_pem(&key, &key)
+        .is_err());
+    // Certificate given as key.
+    assert!(TlsOptions::default()
+        .client_identity_pem(&cert, &cert)
+        .is_err());
+}
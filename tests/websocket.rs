@@ -1,11 +1,444 @@
+use libturms::auth::AuthMethod;
+use libturms::error::{ErrorType, IoError};
 use libturms::websocket::*;
+use std::future::Future;
+use tokio::time::Duration;
 
 const LOCAL_URL: &str = "http://localhost:4000";
 
-#[test]
-fn assert_connect() {
+#[tokio::test]
+#[ignore = "requires a Turms discovery server on localhost"]
+async fn assert_connect() {
     let _ws = WebSocket::new(LOCAL_URL)
         .unwrap()
         .connect("user", None)
+        .await
+        .unwrap();
+}
+
+#[test]
+fn assert_reconnect_delay() {
+    let policy = ReconnectPolicy::default().max_delay(Duration::from_secs(10));
+
+    assert_eq!(policy.delay(0), Some(Duration::from_secs(1)));
+    assert_eq!(policy.delay(3), Some(Duration::from_secs(8)));
+    assert_eq!(policy.delay(4), Some(Duration::from_secs(10)));
+    assert_eq!(policy.delay(usize::MAX), Some(Duration::from_secs(10)));
+
+    let policy = policy.max_attempts(2);
+
+    assert!(policy.delay(1).is_some());
+    assert!(policy.delay(2).is_none());
+}
+
+/// Start a Phoenix-like server replying `ok` to every message.
+async fn reply_server() -> String {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+        while let Some(Ok(message)) = socket.next().await {
+            // Skip heartbeats.
+            let Message::Text(text) = message else {
+                continue;
+            };
+            let message: serde_json::Value =
+                serde_json::from_str(&text).unwrap();
+            let reply = serde_json::json!({
+                "topic": message["topic"],
+                "event": "phx_reply",
+                "payload": { "status": "ok" },
+                "ref": message["ref"],
+            });
+            socket.send(Message::text(reply.to_string())).await.unwrap();
+        }
+    });
+
+    format!("http://{address}")
+}
+
+/// Connect `ws` with a placeholder token, returning its connection future.
+async fn connect_with_token(
+    ws: WebSocket,
+) -> (impl Future<Output = DiscoveryDisconnected>, WebSocket) {
+    ws.connect_with(AuthMethod::Token("token".into()))
+        .await
+        .unwrap()
+}
+
+/// Connect `ws` with a placeholder token, handling connection in
+/// background.
+async fn connect(ws: WebSocket) -> WebSocket {
+    let (handler, ws) = connect_with_token(ws).await;
+    tokio::spawn(handler);
+    ws
+}
+
+#[tokio::test]
+async fn assert_send_and_wait() {
+    use libturms::models::phoenix::{Event, Message};
+
+    let url = reply_server().await;
+    let mut ws = connect(WebSocket::new(url).unwrap()).await;
+
+    let message =
+        Message::<String>::default().event(Event::Custom("ping".into()));
+    let reply = ws
+        .send_and_wait(message, Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    assert_eq!(reply.event, Event::Reply);
+    // Reference `0` was used to join lobby.
+    assert_eq!(reply.reference, Some(1));
+    assert_eq!(ws.current_ref(), 2);
+    assert_eq!(reply.payload["status"], "ok");
+}
+
+#[tokio::test]
+async fn assert_join_and_leave_topic() {
+    let url = reply_server().await;
+    let mut ws = connect(WebSocket::new(url).unwrap()).await;
+
+    ws.join_topic("conversation:42").await.unwrap();
+    assert_eq!(ws.topics().await, vec!["conversation:42"]);
+
+    ws.leave_topic("conversation:42").await.unwrap();
+    assert!(ws.topics().await.is_empty());
+}
+
+#[tokio::test]
+async fn assert_close() {
+    use libturms::websocket::ConnectionState;
+
+    let url = reply_server().await;
+    let (handler, ws) = connect_with_token(WebSocket::new(url).unwrap()).await;
+    let state = ws.state();
+    let handler = tokio::spawn(handler);
+
+    ws.close().await.unwrap();
+
+    let disconnected = tokio::time::timeout(Duration::from_secs(5), handler)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(disconnected.attempts, 0);
+    assert!(disconnected.error.is_none());
+    assert_eq!(*state.borrow(), ConnectionState::Disconnected);
+}
+
+#[test]
+fn assert_invalid_builders() {
+    let ws = WebSocket::new(LOCAL_URL).unwrap();
+
+    assert!(ws.header("bad header", "value").is_err());
+    let ws = WebSocket::new(LOCAL_URL).unwrap();
+    assert!(ws.proxy("socks5://localhost:1080").is_err());
+}
+
+#[tokio::test]
+#[allow(clippy::result_large_err)] // Callback signature is from tungstenite.
+async fn assert_custom_header_through_proxy() {
+    use tokio::io::{copy_bidirectional, AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::oneshot;
+    use tokio_tungstenite::tungstenite::handshake::server::{
+        Request, Response,
+    };
+
+    // Server recording the `User-Agent` of the handshake.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, user_agent) = oneshot::channel();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let callback = |request: &Request, response: Response| {
+            let _ = sender.send(request.headers()["user-agent"].clone());
+            Ok(response)
+        };
+        let _socket = tokio_tungstenite::accept_hdr_async(stream, callback)
+            .await
+            .unwrap();
+        std::future::pending::<()>().await;
+    });
+
+    // Proxy accepting a single `CONNECT` request.
+    let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy_address = proxy.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut client, _) = proxy.accept().await.unwrap();
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            request.push(client.read_u8().await.unwrap());
+        }
+        let request = String::from_utf8(request).unwrap();
+        assert!(request.starts_with(&format!("CONNECT {address} ")));
+
+        let mut server = TcpStream::connect(address).await.unwrap();
+        client
+            .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+            .await
+            .unwrap();
+        let _ = copy_bidirectional(&mut client, &mut server).await;
+    });
+
+    let _ws = connect(
+        WebSocket::new(format!("http://{address}"))
+            .unwrap()
+            .header("User-Agent", "libturms-test")
+            .unwrap()
+            .proxy(&format!("http://{proxy_address}"))
+            .unwrap(),
+    )
+    .await;
+
+    assert_eq!(user_agent.await.unwrap(), "libturms-test");
+}
+
+#[tokio::test]
+async fn assert_encoded_proxy_credentials() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+
+    // Proxy recording `Proxy-Authorization`, then refusing the tunnel.
+    let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy_address = proxy.local_addr().unwrap();
+    let (sender, authorization) = oneshot::channel();
+    tokio::spawn(async move {
+        let (mut client, _) = proxy.accept().await.unwrap();
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            request.push(client.read_u8().await.unwrap());
+        }
+        let request = String::from_utf8(request).unwrap();
+        let header = request
+            .lines()
+            .find_map(|line| line.strip_prefix("Proxy-Authorization: "))
+            .map(str::to_owned);
+        let _ = sender.send(header);
+        client
+            .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+            .await
+            .unwrap();
+    });
+
+    let result = WebSocket::new("http://127.0.0.1:1")
+        .unwrap()
+        .proxy(&format!("http://user:p%40ss%3Aw%25rd@{proxy_address}"))
+        .unwrap()
+        .connect_with(AuthMethod::Token("token".into()))
+        .await;
+
+    assert!(result.is_err());
+    // `user:p@ss:w%rd`.
+    assert_eq!(
+        authorization.await.unwrap().as_deref(),
+        Some("Basic dXNlcjpwQHNzOnclcmQ=")
+    );
+}
+
+#[tokio::test]
+async fn assert_rate_limit() {
+    use libturms::models::phoenix::{Event, Message};
+    use std::num::NonZeroU32;
+
+    let url = reply_server().await;
+    let limit = NonZeroU32::new(5).unwrap();
+    let mut ws = connect(WebSocket::new(url).unwrap().rate_limit(limit)).await;
+
+    let start = tokio::time::Instant::now();
+    // First 5 messages are sent at once, then 1 every 200 ms.
+    for _ in 0..7 {
+        let message =
+            Message::<String>::default().event(Event::Custom("ping".into()));
+        ws.send(message).await.unwrap();
+    }
+
+    assert!(start.elapsed() >= Duration::from_millis(350));
+}
+
+#[tokio::test]
+async fn assert_reconnect_after_drop() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, rejoined) = oneshot::channel();
+
+    tokio::spawn(async move {
+        // Reply to every message, drop the connection once topic is joined.
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        while let Some(Ok(message)) = socket.next().await {
+            let Message::Text(text) = message else {
+                continue;
+            };
+            let message: serde_json::Value =
+                serde_json::from_str(&text).unwrap();
+            let reply = serde_json::json!({
+                "topic": message["topic"],
+                "event": "phx_reply",
+                "payload": { "status": "ok" },
+                "ref": message["ref"],
+            });
+            socket.send(Message::text(reply.to_string())).await.unwrap();
+
+            if message["topic"] == "conversation:42" {
+                break;
+            }
+        }
+        drop(socket);
+
+        // Record joins made after reconnecting.
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        let mut joins = Vec::new();
+        while joins.len() < 2 {
+            if let Some(Ok(Message::Text(text))) = socket.next().await {
+                let message: serde_json::Value =
+                    serde_json::from_str(&text).unwrap();
+                assert_eq!(message["event"], "phx_join");
+                joins.push((message["topic"].clone(), message["ref"].clone()));
+            }
+        }
+        let _ = sender.send(joins);
+        std::future::pending::<()>().await;
+    });
+
+    let mut ws =
+        connect(WebSocket::new(format!("http://{address}")).unwrap()).await;
+    let mut state = ws.state();
+
+    // Reference `0` was used to join lobby.
+    ws.join_topic("conversation:42").await.unwrap();
+
+    let reconnected = state.wait_for(|state| {
+        *state == ConnectionState::Reconnecting { attempt: 1 }
+    });
+    tokio::time::timeout(Duration::from_secs(5), reconnected)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let connected =
+        state.wait_for(|state| *state == ConnectionState::Connected);
+    tokio::time::timeout(Duration::from_secs(5), connected)
+        .await
+        .unwrap()
         .unwrap();
+
+    // Lobby and topic are joined again, with fresh references.
+    let joins = tokio::time::timeout(Duration::from_secs(5), rejoined)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        joins,
+        vec![
+            (serde_json::json!(""), serde_json::json!("2")),
+            (serde_json::json!("conversation:42"), serde_json::json!("3")),
+        ]
+    );
+    assert_eq!(ws.current_ref(), 4);
+}
+
+#[tokio::test]
+async fn assert_give_up_reconnecting() {
+    use futures_util::StreamExt;
+    use tokio::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    // Accept a single connection, then stop listening.
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        drop(listener);
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        socket.next().await;
+    });
+
+    let (handler, _ws) = connect_with_token(
+        WebSocket::new(format!("http://{address}"))
+            .unwrap()
+            .reconnect_policy(ReconnectPolicy::default().max_attempts(2)),
+    )
+    .await;
+
+    // Attempts wait 1 then 2 seconds.
+    let disconnected = tokio::time::timeout(Duration::from_secs(10), handler)
+        .await
+        .unwrap();
+    assert_eq!(disconnected.attempts, 2);
+    assert!(disconnected.error.is_some());
+}
+
+#[tokio::test]
+async fn assert_heartbeats_are_not_messages() {
+    use futures_util::StreamExt;
+    use tokio::net::TcpListener;
+
+    // Server only answering pings.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        while socket.next().await.is_some() {}
+    });
+
+    let ws = connect(
+        WebSocket::new(format!("http://{address}"))
+            .unwrap()
+            .heartbeat_interval(Duration::from_millis(100)),
+    )
+    .await;
+    let mut messages = ws.messages();
+
+    let message =
+        tokio::time::timeout(Duration::from_millis(650), messages.next()).await;
+    assert!(message.is_err(), "unexpected message: {message:?}");
+}
+
+#[tokio::test]
+async fn assert_max_queued_after_connect() {
+    use libturms::models::phoenix::{Event, Message};
+    use std::num::NonZeroUsize;
+
+    let url = reply_server().await;
+    let ws = connect(WebSocket::new(url).unwrap()).await;
+    let mut ws = ws.max_queued(NonZeroUsize::new(16).unwrap());
+
+    let message =
+        Message::<String>::default().event(Event::Custom("ping".into()));
+    let reply = ws
+        .send_and_wait(message, Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert_eq!(reply.payload["status"], "ok");
+}
+
+#[tokio::test]
+async fn assert_zero_heartbeat_interval() {
+    let result = WebSocket::new(LOCAL_URL)
+        .unwrap()
+        .heartbeat_interval(Duration::ZERO)
+        .connect_with(AuthMethod::Token("token".into()))
+        .await;
+
+    assert!(matches!(
+        result.err().unwrap().etype,
+        ErrorType::InputOutput(IoError::ConfigError)
+    ));
 }